@@ -0,0 +1,181 @@
+// Conversion between Elixir terms and `serde_json::Value`.
+//
+// Plain `serde_json` round-tripping loses information that matters once
+// components start exchanging non-trivial data: binaries and tuples have no
+// native JSON representation, and naively clamping an unrepresentable
+// float/bignum to `0` silently corrupts the payload instead of surfacing an
+// error. This module tags the lossy cases instead of dropping them.
+//
+// `parse_json_fast` is a separate, explicitly opt-in entry point (wired up
+// by the `process_json` NIF) for callers that pre-serialize large payloads
+// on the Elixir side; `term_to_json`/`process` never auto-detect a binary as
+// JSON, since every Elixir string is also a binary and silently reinterpreting
+// one (`"123"` -> `123`, `"true"` -> `true`, ...) would corrupt ordinary
+// string input.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use num_bigint::BigInt;
+use rustler::types::binary::{Binary, OwnedBinary};
+use rustler::types::tuple::{get_tuple, make_tuple};
+use rustler::{Encoder, Env, Error, Term};
+use serde_json::{json, Value};
+
+// Tag keys/markers are wrapped in NUL bytes, which essentially never appear
+// in real JSON object keys or tuple-tag strings, so that inbound JSON from
+// `parse_json_fast` (untrusted, unlike terms decoded from Elixir) can't
+// plausibly collide with the tagging scheme and get misread as a binary,
+// tuple, or bigint it never was.
+
+// Elixir binaries round-trip as `{"\0__lux_binary__\0": "<base64>"}` rather
+// than being dropped or coerced into a list of byte integers. Only binaries
+// that are *not* valid UTF-8 take this path — ordinary Elixir strings are
+// binaries too, but decode as JSON strings first (see `term_to_json`).
+const BINARY_TAG: &str = "\0__lux_binary__\0";
+// Tuples have no JSON equivalent, so they round-trip as a tagged 2-element
+// array: `["\0__lux_tuple__\0", [elements...]]`.
+const TUPLE_TAG: &str = "\0__lux_tuple__\0";
+// Integers that don't fit in an `i64` round-trip as their exact decimal
+// string (arbitrary precision, via `BigInt`) instead of being truncated or
+// coerced to `0`.
+const BIGINT_TAG: &str = "\0__lux_bigint__\0";
+
+// Recursively converts an Elixir term into a `serde_json::Value`, tagging
+// binaries, tuples, and out-of-range integers so they survive a round trip
+// through `json_to_term` instead of being silently coerced or dropped.
+pub fn term_to_json<'a>(env: Env<'a>, term: Term<'a>) -> Result<Value, Error> {
+    if let Ok(elements) = get_tuple(term) {
+        let json_elements = elements
+            .into_iter()
+            .map(|t| term_to_json(env, t))
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(json!([TUPLE_TAG, json_elements]));
+    }
+
+    // Elixir strings are binaries under the hood, so the UTF-8 `String`
+    // decode must be tried before the opaque `Binary` fallback below —
+    // otherwise every string (including JSON-RPC `method` names, tool
+    // names, ...) would be wrapped as a binary tag instead of staying a
+    // plain JSON string.
+    if let Ok(s) = term.decode::<String>() {
+        return Ok(Value::String(s));
+    }
+
+    if let Ok(binary) = term.decode::<Binary>() {
+        return Ok(json!({ BINARY_TAG: STANDARD.encode(binary.as_slice()) }));
+    }
+
+    if term.is_map() {
+        let map: std::collections::HashMap<String, Term> = term.decode()?;
+        let mut json_map = serde_json::Map::new();
+        for (key, value) in map {
+            json_map.insert(key, term_to_json(env, value)?);
+        }
+        Ok(Value::Object(json_map))
+    } else if term.is_list() {
+        let list: Vec<Term> = term.decode()?;
+        let json_list = list
+            .into_iter()
+            .map(|item| term_to_json(env, item))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Value::Array(json_list))
+    } else if term.is_number() {
+        if let Ok(n) = term.decode::<i64>() {
+            Ok(Value::Number(n.into()))
+        } else if let Ok(n) = term.decode::<BigInt>() {
+            Ok(json!({ BIGINT_TAG: n.to_string() }))
+        } else if let Ok(n) = term.decode::<f64>() {
+            serde_json::Number::from_f64(n)
+                .map(Value::Number)
+                .ok_or_else(|| Error::Term(Box::new(format!("cannot represent {} as JSON", n))))
+        } else {
+            Err(Error::Term(Box::new("unsupported numeric term".to_string())))
+        }
+    } else if let Ok(b) = term.decode::<bool>() {
+        Ok(Value::Bool(b))
+    } else if term.is_atom() {
+        match term.atom_to_string() {
+            Ok(atom_str) if atom_str == "nil" => Ok(Value::Null),
+            Ok(atom_str) => Ok(Value::String(atom_str)),
+            Err(_) => Err(Error::Term(Box::new("unsupported atom term".to_string()))),
+        }
+    } else {
+        Err(Error::Term(Box::new("unsupported term type".to_string())))
+    }
+}
+
+// Parses a buffer the caller asserts already holds a serialized JSON
+// document, using `simd-json`'s in-place mutable parsing instead of
+// `serde_json::from_slice`. Roughly doubles deserialization throughput on
+// large inputs, which matters for the `process_json` NIF's bulk-payload use
+// case — but unlike `term_to_json`, it is never applied implicitly to an
+// arbitrary binary/string, since there is no way to tell "JSON-as-text" and
+// "opaque/string data" apart without the caller saying which one it is.
+pub fn parse_json_fast(bytes: &mut [u8]) -> Result<Value, Error> {
+    simd_json::serde::from_slice(bytes).map_err(|e| Error::Term(Box::new(format!("invalid JSON: {}", e))))
+}
+
+// Recursively converts a `serde_json::Value` back into an Elixir term,
+// reconstructing tagged binaries/tuples/bignums emitted by `term_to_json`.
+pub fn json_to_term<'a>(env: Env<'a>, value: &Value) -> Result<Term<'a>, Error> {
+    if let Value::Object(map) = value {
+        if map.len() == 1 {
+            if let Some(b64) = map.get(BINARY_TAG).and_then(|v| v.as_str()) {
+                let bytes = STANDARD
+                    .decode(b64)
+                    .map_err(|e| Error::Term(Box::new(format!("invalid base64 binary: {}", e))))?;
+                let mut binary = OwnedBinary::new(bytes.len())
+                    .ok_or_else(|| Error::Term(Box::new("failed to allocate binary".to_string())))?;
+                binary.as_mut_slice().copy_from_slice(&bytes);
+                return Ok(binary.release(env).encode(env));
+            }
+
+            if let Some(digits) = map.get(BIGINT_TAG).and_then(|v| v.as_str()) {
+                let n: BigInt = digits
+                    .parse()
+                    .map_err(|_| Error::Term(Box::new(format!("invalid bigint literal: {}", digits))))?;
+                return Ok(n.encode(env));
+            }
+        }
+    }
+
+    if let Value::Array(items) = value {
+        if let [Value::String(tag), Value::Array(elements)] = items.as_slice() {
+            if tag == TUPLE_TAG {
+                let terms = elements
+                    .iter()
+                    .map(|v| json_to_term(env, v))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(make_tuple(env, &terms).encode(env));
+            }
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let entries = map
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), json_to_term(env, v)?)))
+                .collect::<Result<Vec<(String, Term)>, Error>>()?;
+            Ok(entries.encode(env))
+        }
+        Value::Array(items) => {
+            let terms = items
+                .iter()
+                .map(|v| json_to_term(env, v))
+                .collect::<Result<Vec<Term>, Error>>()?;
+            Ok(terms.encode(env))
+        }
+        Value::String(s) => Ok(s.encode(env)),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(i.encode(env))
+            } else if let Some(f) = n.as_f64() {
+                Ok(f.encode(env))
+            } else {
+                Err(Error::Term(Box::new(format!("cannot represent JSON number {} as a term", n))))
+            }
+        }
+        Value::Bool(b) => Ok(b.encode(env)),
+        Value::Null => Ok(rustler::types::atom::nil().encode(env)),
+    }
+}
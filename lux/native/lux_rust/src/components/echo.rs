@@ -28,4 +28,12 @@ impl LuxComponent for EchoComponent {
     async fn cleanup(&self) -> Result<(), Error> {
         Ok(())
     }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    async fn process_stream(&self, _stream_id: &str, chunk: Value, _is_last: bool) -> Result<Option<Value>, Error> {
+        Ok(Some(chunk))
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,162 @@
+use crate::{build_component, LuxComponent};
+use async_trait::async_trait;
+use rustler::Error;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message },
+        "id": id,
+    })
+}
+
+// Transport-agnostic JSON-RPC 2.0 envelope on top of the existing component
+// dispatch. `process` accepts either a single request object or a batch
+// array and routes `method` to a configured map of handler sub-components,
+// wrapping their results (or failures) in a spec-compliant response.
+pub struct JsonRpcComponent {
+    handlers: HashMap<String, Arc<dyn LuxComponent>>,
+}
+
+impl fmt::Debug for JsonRpcComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonRpcComponent")
+            .field("handlers", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl JsonRpcComponent {
+    pub fn new(config: Value) -> Result<Self, Error> {
+        let handler_specs = config
+            .get("handlers")
+            .and_then(|v| v.as_object())
+            .ok_or_else(|| Error::Term(Box::new("jsonrpc config is missing a \"handlers\" object".to_string())))?;
+
+        let mut handlers = HashMap::new();
+        for (method, spec) in handler_specs {
+            let component_name = spec
+                .get("component")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| Error::Term(Box::new(format!("handler \"{}\" is missing a \"component\" name", method))))?;
+            let component_config = spec.get("config").cloned().unwrap_or(Value::Null);
+            handlers.insert(method.clone(), build_component(component_name, component_config)?);
+        }
+
+        Ok(Self { handlers })
+    }
+
+    // Dispatches a single request object, returning `None` for notifications
+    // (requests with no `id`), which per the spec must produce no response
+    // element at all.
+    async fn dispatch(&self, request: Value) -> Option<Value> {
+        let id_for_errors = request.get("id").cloned().unwrap_or(Value::Null);
+
+        let obj = match request.as_object() {
+            Some(o) => o,
+            None => return Some(error_response(id_for_errors, INVALID_REQUEST, "Invalid Request")),
+        };
+
+        let has_id = obj.contains_key("id");
+        let id = obj.get("id").cloned().unwrap_or(Value::Null);
+
+        let version_ok = obj.get("jsonrpc").and_then(|v| v.as_str()) == Some("2.0");
+        let method = obj.get("method").and_then(|v| v.as_str()).map(str::to_string);
+
+        // A Notification (no `id`) must never produce a response element,
+        // even when the request itself is malformed — so every error return
+        // from here on is gated behind `has_id`.
+        let method = match (version_ok, method) {
+            (true, Some(method)) => method,
+            _ => return has_id.then(|| error_response(id, INVALID_REQUEST, "Invalid Request")),
+        };
+
+        let params = obj.get("params").cloned().unwrap_or(Value::Null);
+        if !matches!(params, Value::Null | Value::Array(_) | Value::Object(_)) {
+            return has_id.then(|| error_response(id, INVALID_PARAMS, "Invalid params"));
+        }
+
+        let handler = match self.handlers.get(&method) {
+            Some(handler) => handler,
+            None => return has_id.then(|| error_response(id, METHOD_NOT_FOUND, "Method not found")),
+        };
+
+        let result = handler.process(params).await;
+
+        if !has_id {
+            // Notification: run for effect, never reply.
+            return None;
+        }
+
+        match result {
+            Ok(value) => Some(json!({ "jsonrpc": "2.0", "result": value, "id": id })),
+            Err(_) => Some(error_response(id, INTERNAL_ERROR, "Internal error")),
+        }
+    }
+}
+
+#[async_trait]
+impl LuxComponent for JsonRpcComponent {
+    async fn initialize(&self) -> Result<(), Error> {
+        for handler in self.handlers.values() {
+            handler.initialize().await?;
+        }
+        Ok(())
+    }
+
+    async fn process(&self, input: Value) -> Result<Value, Error> {
+        // Allow callers to hand over a raw JSON-RPC payload string as well
+        // as an already-decoded term. `codec::term_to_json` keeps an Elixir
+        // string as a JSON string, so any string input takes this branch:
+        // one handed to the `process`/`process_chunk` NIFs as well as one
+        // passed in when `JsonRpcComponent` is driven as a nested
+        // sub-component (e.g. a `tool_loop`/`jsonrpc` handler).
+        let value = match &input {
+            Value::String(raw) => match serde_json::from_str::<Value>(raw) {
+                Ok(parsed) => parsed,
+                Err(_) => return Ok(error_response(Value::Null, PARSE_ERROR, "Parse error")),
+            },
+            _ => input,
+        };
+
+        match value {
+            Value::Array(requests) => {
+                if requests.is_empty() {
+                    return Ok(error_response(Value::Null, INVALID_REQUEST, "Invalid Request"));
+                }
+
+                let mut responses = Vec::new();
+                for request in requests {
+                    if let Some(response) = self.dispatch(request).await {
+                        responses.push(response);
+                    }
+                }
+
+                // An all-notifications batch produces no response at all.
+                if responses.is_empty() {
+                    Ok(Value::Null)
+                } else {
+                    Ok(Value::Array(responses))
+                }
+            }
+            other => Ok(self.dispatch(other).await.unwrap_or(Value::Null)),
+        }
+    }
+
+    async fn cleanup(&self) -> Result<(), Error> {
+        for handler in self.handlers.values() {
+            handler.cleanup().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+mod echo;
+mod jsonrpc;
+mod tool_loop;
+
+pub use echo::EchoComponent;
+pub use jsonrpc::JsonRpcComponent;
+pub use tool_loop::ToolLoopComponent;
@@ -0,0 +1,124 @@
+use crate::{build_component, LuxComponent};
+use async_trait::async_trait;
+use rustler::Error;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+// Used when a `tool_loop` config omits `max_steps`.
+const DEFAULT_MAX_STEPS: u64 = 10;
+
+// Orchestrates a multi-step "agent" loop over other registered components.
+// One of the configured tools acts as the planner: each step it is handed
+// the current payload and the trace so far, and replies with either
+// `{"tool_calls": [{"tool": ..., "args": ...}, ...]}` to keep going or
+// anything else to signal it is done (in which case `payload`, if present
+// on its reply, becomes the final payload). Every call and its result is
+// recorded in `steps` for the caller.
+pub struct ToolLoopComponent {
+    planner: String,
+    tools: HashMap<String, Arc<dyn LuxComponent>>,
+    max_steps: u64,
+}
+
+impl fmt::Debug for ToolLoopComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolLoopComponent")
+            .field("planner", &self.planner)
+            .field("tools", &self.tools.keys().collect::<Vec<_>>())
+            .field("max_steps", &self.max_steps)
+            .finish()
+    }
+}
+
+impl ToolLoopComponent {
+    pub fn new(config: Value) -> Result<Self, Error> {
+        let planner = config
+            .get("planner")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| Error::Term(Box::new("tool_loop config is missing a \"planner\" tool name".to_string())))?
+            .to_string();
+
+        let tool_specs = config
+            .get("tools")
+            .and_then(|v| v.as_object())
+            .ok_or_else(|| Error::Term(Box::new("tool_loop config is missing a \"tools\" object".to_string())))?;
+
+        let mut tools = HashMap::new();
+        for (name, spec) in tool_specs {
+            let component_name = spec
+                .get("component")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| Error::Term(Box::new(format!("tool \"{}\" is missing a \"component\" name", name))))?;
+            let component_config = spec.get("config").cloned().unwrap_or(Value::Null);
+            tools.insert(name.clone(), build_component(component_name, component_config)?);
+        }
+
+        if !tools.contains_key(&planner) {
+            return Err(Error::Term(Box::new(format!("planner tool \"{}\" is not present in \"tools\"", planner))));
+        }
+
+        let max_steps = config.get("max_steps").and_then(|v| v.as_u64()).unwrap_or(DEFAULT_MAX_STEPS);
+
+        Ok(Self { planner, tools, max_steps })
+    }
+}
+
+#[async_trait]
+impl LuxComponent for ToolLoopComponent {
+    async fn initialize(&self) -> Result<(), Error> {
+        for tool in self.tools.values() {
+            tool.initialize().await?;
+        }
+        Ok(())
+    }
+
+    async fn process(&self, input: Value) -> Result<Value, Error> {
+        let mut payload = input;
+        let mut steps = Vec::new();
+
+        for _ in 0..self.max_steps {
+            // Unwrap: validated to be present in `tools` at construction time.
+            let planner = self.tools.get(&self.planner).unwrap();
+            let plan = planner
+                .process(json!({ "payload": payload, "steps": steps }))
+                .await?;
+
+            let tool_calls = plan.get("tool_calls").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+            if tool_calls.is_empty() {
+                let payload = plan.get("payload").cloned().unwrap_or(plan);
+                return Ok(json!({ "payload": payload, "steps": steps }));
+            }
+
+            let mut results = Vec::new();
+            for call in &tool_calls {
+                let tool_name = call
+                    .get("tool")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| Error::Term(Box::new("tool call is missing a \"tool\" name".to_string())))?;
+                let args = call.get("args").cloned().unwrap_or(Value::Null);
+                let tool = self
+                    .tools
+                    .get(tool_name)
+                    .ok_or_else(|| Error::Term(Box::new(format!("unknown tool \"{}\"", tool_name))))?;
+
+                let result = tool.process(args.clone()).await?;
+                steps.push(json!({ "tool": tool_name, "args": args, "result": result }));
+                results.push(json!({ "tool": tool_name, "result": result }));
+            }
+
+            payload = json!({ "tool_results": results });
+        }
+
+        // Planner never stopped emitting tool calls within max_steps.
+        Ok(json!({ "payload": payload, "steps": steps, "truncated": true }))
+    }
+
+    async fn cleanup(&self) -> Result<(), Error> {
+        for tool in self.tools.values() {
+            tool.cleanup().await?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
+use rustler::types::binary::Binary;
 use rustler::{Atom, Env, Error, NifStruct, ResourceArc, Term, Encoder, NifResult};
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use async_trait::async_trait;
+use once_cell::sync::OnceCell;
 use tokio::runtime::Runtime;
 use std::fmt;
 
@@ -13,8 +16,10 @@ mod atoms {
     }
 }
 
+mod codec;
 mod components;
-use components::EchoComponent;
+use codec::{json_to_term, parse_json_fast, term_to_json};
+use components::{EchoComponent, JsonRpcComponent, ToolLoopComponent};
 
 // Component trait that defines the interface for all Lux components
 #[async_trait]
@@ -22,13 +27,62 @@ pub trait LuxComponent: Send + Sync + fmt::Debug {
     async fn initialize(&self) -> Result<(), Error>;
     async fn process(&self, input: serde_json::Value) -> Result<serde_json::Value, Error>;
     async fn cleanup(&self) -> Result<(), Error>;
+
+    // Whether `process_stream` does real chunk-at-a-time work. Override
+    // this alongside `process_stream` when a component can produce output
+    // before the last chunk arrives (see `EchoComponent`). Components that
+    // leave this `false` are driven by `ComponentResource`, which buffers
+    // every chunk and calls `process` once on the merged payload instead.
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+
+    // Processes one chunk of the stream identified by `stream_id`; `is_last`
+    // marks the final chunk (which may carry no data of its own, just the
+    // end-of-stream signal). `stream_id` is the same value `process_chunk`
+    // and `finalize` were called with, so a component that keeps its own
+    // per-stream state can key it the same way `ComponentResource` keys its
+    // buffer, and multiple concurrent streams on one resource stay isolated.
+    // Returning `Ok(None)` means "no output yet". The default returns
+    // `Ok(None)` for every non-final chunk, discarding it, and passes only
+    // the chunk given with `is_last` to `process`; `finalize` passes `null`
+    // there. It is only reachable for components that opt in via
+    // `supports_streaming`; earlier chunks are not buffered for it.
+    async fn process_stream(&self, stream_id: &str, chunk: serde_json::Value, is_last: bool) -> Result<Option<serde_json::Value>, Error> {
+        let _ = stream_id;
+        if is_last {
+            Ok(Some(self.process(chunk).await?))
+        } else {
+            Ok(None)
+        }
+    }
 }
 
-// Component resource that holds the runtime and component instance
+// Process-wide multi-threaded runtime shared by every ComponentResource.
+// A single runtime (rather than one per resource) means `process` calls no
+// longer serialize on a per-component lock, and dirty-scheduling `process`
+// (see below) keeps `block_on` off the regular BEAM schedulers.
+static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+
+fn runtime() -> Result<&'static Runtime, Error> {
+    RUNTIME.get_or_try_init(|| {
+        Runtime::new().map_err(|e| Error::Term(Box::new(format!("Runtime error: {}", e))))
+    })
+}
+
+// Component resource that holds the component instance. The runtime itself
+// lives in the process-wide `RUNTIME` cell so that concurrent `process`
+// calls on the same resource can run in parallel instead of queuing behind
+// a lock. `stream_buffers` accumulates chunks handed to `process_chunk` for
+// components that don't implement real streaming, keyed by the caller's
+// `stream_id` so that multiple streams against the same resource (now that
+// `process`/`process_chunk` can run concurrently on one `ResourceArc`) don't
+// intermix each other's chunks; `finalize` removes and merges one stream's
+// entry into a single `process` call.
 #[derive(Debug)]
 pub struct ComponentResource {
-    runtime: Mutex<Runtime>,
-    component: Box<dyn LuxComponent>,
+    component: Arc<dyn LuxComponent>,
+    stream_buffers: Mutex<HashMap<String, Vec<serde_json::Value>>>,
 }
 
 // Component configuration struct that can be passed from Elixir
@@ -42,134 +96,148 @@ pub struct ComponentConfig<'a> {
 // Initialize the runtime and component
 #[rustler::nif]
 fn initialize<'a>(env: Env<'a>, config: ComponentConfig<'a>) -> Result<Term<'a>, Error> {
-    let runtime = Runtime::new().map_err(|e| Error::Term(Box::new(format!("Runtime error: {}", e))))?;
-    
     // Create component instance based on name (to be implemented by specific components)
     let component = create_component(env, &config)?;
-    
+
     // Initialize the component
-    runtime.block_on(async {
+    runtime()?.block_on(async {
         component.initialize().await
     })?;
-    
+
     let resource = ResourceArc::new(ComponentResource {
-        runtime: Mutex::new(runtime),
         component,
+        stream_buffers: Mutex::new(HashMap::new()),
     });
-    
+
     Ok(resource.encode(env))
 }
 
-// Process input through the component
-#[rustler::nif]
+// Process input through the component. Runs on a dirty IO scheduler because
+// `block_on` blocks the calling OS thread for the duration of the call, and
+// the component itself may be doing blocking or long-running work; keeping
+// that off the regular BEAM schedulers avoids stalling the whole VM.
+// `ComponentResource::component` is an `Arc`, so multiple `process` calls on
+// the same resource can now execute concurrently instead of queuing behind
+// a per-resource lock.
+#[rustler::nif(schedule = "DirtyIo")]
 fn process<'a>(env: Env<'a>, resource: ResourceArc<ComponentResource>, input: Term<'a>) -> NifResult<Term<'a>> {
     // Convert input Term to serde_json::Value
     let input_value = term_to_json(env, input)?;
-    
-    let runtime = &resource.runtime.lock().unwrap();
-    let result = runtime.block_on(async {
-        resource.component.process(input_value).await
+
+    let component = resource.component.clone();
+    let result = runtime()?.block_on(async move {
+        component.process(input_value).await
     })?;
-    
+
     // Convert result back to Term
     json_to_term(env, &result)
 }
 
+// Explicit opt-in counterpart to `process` for callers that have already
+// serialized their payload to JSON on the Elixir side (e.g. `Jason.encode!`)
+// instead of building nested maps/lists, parsing it with the `simd-json`
+// fast path rather than walking it term-by-term. Unlike `process`, which
+// never auto-detects a binary/string as JSON, this NIF *requires* `json` to
+// be one so there's no ambiguity about the caller's intent.
+#[rustler::nif(schedule = "DirtyIo")]
+fn process_json<'a>(env: Env<'a>, resource: ResourceArc<ComponentResource>, json: Binary<'a>) -> NifResult<Term<'a>> {
+    let mut bytes = json.as_slice().to_vec();
+    let input_value = parse_json_fast(&mut bytes)?;
+
+    let component = resource.component.clone();
+    let result = runtime()?.block_on(async move {
+        component.process(input_value).await
+    })?;
+
+    json_to_term(env, &result)
+}
+
+// Pushes one chunk of a stream identified by `stream_id` (minted by the
+// caller, e.g. from a reference; must be unique per in-flight stream on this
+// resource). Components that override `process_stream`
+// (`supports_streaming() == true`) see the chunk immediately and may return
+// output for it right away; everything else is buffered per-`stream_id` on
+// `ComponentResource` until `finalize` runs `process` over that stream's
+// chunks. Returns `nil` when there is no output yet.
+#[rustler::nif(schedule = "DirtyIo")]
+fn process_chunk<'a>(env: Env<'a>, resource: ResourceArc<ComponentResource>, stream_id: String, chunk: Term<'a>) -> NifResult<Term<'a>> {
+    let chunk_value = term_to_json(env, chunk)?;
+    let component = resource.component.clone();
+
+    if component.supports_streaming() {
+        let result = runtime()?.block_on(async move {
+            component.process_stream(&stream_id, chunk_value, false).await
+        })?;
+
+        return match result {
+            Some(value) => json_to_term(env, &value),
+            None => Ok(rustler::types::atom::nil().encode(env)),
+        };
+    }
+
+    resource.stream_buffers.lock().unwrap().entry(stream_id).or_default().push(chunk_value);
+    Ok(rustler::types::atom::nil().encode(env))
+}
+
+// Ends the stream identified by `stream_id` and returns the final result:
+// one last `process_stream(_, is_last = true)` call for components that
+// stream natively, or a single `process` call over that stream's buffered
+// chunks for everything else. Removes the stream's buffer entry either way.
+#[rustler::nif(schedule = "DirtyIo")]
+fn finalize<'a>(env: Env<'a>, resource: ResourceArc<ComponentResource>, stream_id: String) -> NifResult<Term<'a>> {
+    let component = resource.component.clone();
+
+    let result = if component.supports_streaming() {
+        runtime()?.block_on(async move {
+            component.process_stream(&stream_id, serde_json::Value::Null, true).await
+        })?.unwrap_or(serde_json::Value::Null)
+    } else {
+        let mut chunks = resource.stream_buffers.lock().unwrap().remove(&stream_id).unwrap_or_default();
+        // A single chunk is passed through as-is rather than wrapped in an
+        // array: components interpret their `process` input directly (e.g.
+        // `JsonRpcComponent` treats a top-level array as a batch), so only
+        // genuinely multi-chunk streams should take on array shape.
+        let payload = if chunks.len() == 1 {
+            chunks.remove(0)
+        } else {
+            serde_json::Value::Array(chunks)
+        };
+        runtime()?.block_on(async move {
+            component.process(payload).await
+        })?
+    };
+
+    json_to_term(env, &result)
+}
+
 // Cleanup component resources
 #[rustler::nif]
 fn cleanup(resource: ResourceArc<ComponentResource>) -> NifResult<Atom> {
-    let runtime = &resource.runtime.lock().unwrap();
-    runtime.block_on(async {
+    runtime()?.block_on(async {
         resource.component.cleanup().await
     })?;
-    
+
     Ok(atoms::ok())
 }
 
 // Helper function to create component instances
-fn create_component<'a>(env: Env<'a>, config: &ComponentConfig<'a>) -> Result<Box<dyn LuxComponent>, Error> {
+fn create_component<'a>(env: Env<'a>, config: &ComponentConfig<'a>) -> Result<Arc<dyn LuxComponent>, Error> {
     let config_json = term_to_json(env, config.config)?;
-    
-    match config.name.as_str() {
-        "echo" => Ok(Box::new(EchoComponent::new(config_json))),
-        _ => Err(Error::Term(Box::new(atoms::not_implemented())))
-    }
-}
 
-// Helper function to convert Term to serde_json::Value
-fn term_to_json<'a>(env: Env<'a>, term: Term<'a>) -> Result<serde_json::Value, Error> {
-    if term.is_map() {
-        let map: std::collections::HashMap<String, Term> = term.decode()?;
-        let mut json_map = serde_json::Map::new();
-        for (key, value) in map {
-            let value_json = term_to_json(env, value)?;
-            json_map.insert(key, value_json);
-        }
-        Ok(serde_json::Value::Object(json_map))
-    } else if term.is_list() {
-        let list: Vec<Term> = term.decode().unwrap_or_default();
-        let mut json_list = Vec::new();
-        for item in list {
-            json_list.push(term_to_json(env, item)?);
-        }
-        Ok(serde_json::Value::Array(json_list))
-    } else if term.is_number() {
-        if let Ok(n) = term.decode::<i64>() {
-            Ok(serde_json::Value::Number(n.into()))
-        } else if let Ok(n) = term.decode::<f64>() {
-            if let Some(num) = serde_json::Number::from_f64(n) {
-                Ok(serde_json::Value::Number(num))
-            } else {
-                Ok(serde_json::Value::Number(0.into()))
-            }
-        } else {
-            Ok(serde_json::Value::Number(0.into()))
-        }
-    } else if let Ok(s) = term.decode::<String>() {
-        Ok(serde_json::Value::String(s))
-    } else if let Ok(b) = term.decode::<bool>() {
-        Ok(serde_json::Value::Bool(b))
-    } else if term.is_atom() {
-        if let Ok(atom_str) = term.atom_to_string() {
-            if atom_str == "nil" {
-                Ok(serde_json::Value::Null)
-            } else {
-                Ok(serde_json::Value::String(atom_str))
-            }
-        } else {
-            Ok(serde_json::Value::Null)
-        }
-    } else {
-        Ok(serde_json::Value::Null)
-    }
+    build_component(&config.name, config_json)
 }
 
-// Helper function to convert serde_json::Value to Term
-fn json_to_term<'a>(env: Env<'a>, value: &serde_json::Value) -> Result<Term<'a>, Error> {
-    match value {
-        serde_json::Value::Object(map) => {
-            let map_entries: Vec<(String, Term)> = map
-                .iter()
-                .map(|(k, v)| Ok((k.clone(), json_to_term(env, v)?)))
-                .collect::<Result<_, Error>>()?;
-            Ok(map_entries.encode(env))
-        }
-        serde_json::Value::Array(array) => {
-            let terms: Result<Vec<Term>, Error> = array.iter().map(|v| json_to_term(env, v)).collect();
-            Ok(terms?.encode(env))
-        }
-        serde_json::Value::String(s) => Ok(s.encode(env)),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                Ok(i.encode(env))
-            } else if let Some(f) = n.as_f64() {
-                Ok(f.encode(env))
-            } else {
-                Ok(0i64.encode(env))
-            }
-        }
-        serde_json::Value::Bool(b) => Ok(b.encode(env)),
-        serde_json::Value::Null => Ok(rustler::types::atom::nil().encode(env)),
+// Builds a component instance from its registered name and already-decoded
+// config. Factored out of `create_component` so components that host their
+// own sub-components (e.g. `ToolLoopComponent`) can build those the same
+// way the top-level `initialize` NIF does, without going back through a
+// `Term`.
+pub(crate) fn build_component(name: &str, config_json: serde_json::Value) -> Result<Arc<dyn LuxComponent>, Error> {
+    match name {
+        "echo" => Ok(Arc::new(EchoComponent::new(config_json))),
+        "tool_loop" => Ok(Arc::new(ToolLoopComponent::new(config_json)?)),
+        "jsonrpc" => Ok(Arc::new(JsonRpcComponent::new(config_json)?)),
+        _ => Err(Error::Term(Box::new(atoms::not_implemented())))
     }
 }
 